@@ -4,8 +4,9 @@ use std::ops::{Add, AddAssign};
 
 use ecow::{eco_format, EcoString, EcoVec};
 
-use super::{ops, Args, Func, Value, Vm};
+use super::{ops, Args, Func, Iter, Value, Vm};
 use crate::diag::{bail, At, SourceResult, StrResult};
+use crate::syntax::Span;
 
 /// Create a new [`Array`] from values.
 #[macro_export]
@@ -214,6 +215,128 @@ impl Array {
         Ok(acc)
     }
 
+    /// Fold all of the array's elements into one with a function, using the
+    /// first element as the initial accumulator.
+    ///
+    /// Returns an error if the array is empty.
+    pub fn reduce(&self, vm: &mut Vm, func: Func) -> SourceResult<Value> {
+        if func.argc().map_or(false, |count| count != 2) {
+            bail!(func.span(), "function must have exactly two parameters");
+        }
+        let mut iter = self.iter();
+        let mut acc = iter
+            .next()
+            .ok_or_else(array_is_empty)
+            .at(func.span())?
+            .clone();
+        for item in iter {
+            let args = Args::new(func.span(), [acc, item.clone()]);
+            acc = func.call(vm, args)?;
+        }
+        Ok(acc)
+    }
+
+    /// Add up all values in the array. Returns `default` if the array is
+    /// empty.
+    pub fn sum(&self, default: Value) -> StrResult<Value> {
+        let mut iter = self.iter().cloned();
+        let Some(mut acc) = iter.next() else {
+            return Ok(default);
+        };
+        for item in iter {
+            acc = ops::add(acc, item)?;
+        }
+        Ok(acc)
+    }
+
+    /// Multiply all values in the array together. Returns `default` if the
+    /// array is empty.
+    pub fn product(&self, default: Value) -> StrResult<Value> {
+        let mut iter = self.iter().cloned();
+        let Some(mut acc) = iter.next() else {
+            return Ok(default);
+        };
+        for item in iter {
+            acc = ops::mul(acc, item)?;
+        }
+        Ok(acc)
+    }
+
+    /// Fold all of the array's elements into one with a function, like
+    /// `fold`, but return an array of every intermediate accumulator value
+    /// instead of just the final one.
+    pub fn scan(&self, vm: &mut Vm, init: Value, func: Func) -> SourceResult<Self> {
+        if func.argc().map_or(false, |count| count != 2) {
+            bail!(func.span(), "function must have exactly two parameters");
+        }
+        let mut acc = init;
+        let mut out = EcoVec::with_capacity(self.0.len());
+        for item in self.iter() {
+            let args = Args::new(func.span(), [acc.clone(), item.clone()]);
+            acc = func.call(vm, args)?;
+            out.push(acc.clone());
+        }
+        Ok(Self::from_vec(out))
+    }
+
+    /// Return a new array with duplicate elements removed, keeping the
+    /// first occurrence of each.
+    ///
+    /// Without `key` or `by`, elements are compared for equality directly.
+    /// With `key`, a one-argument projection function is applied to each
+    /// element first and the results are compared instead. With `by`, a
+    /// two-argument function decides whether two elements are duplicates
+    /// of each other.
+    ///
+    /// Unlike the typical "consecutive only" dedup, this removes
+    /// duplicates globally rather than just adjacent ones, so it works
+    /// directly on unsorted input; pair it with `sorted` first to get the
+    /// classic adjacent-dedup behavior.
+    pub fn dedup(&self, vm: &mut Vm, key: Option<Func>, by: Option<Func>) -> SourceResult<Self> {
+        if let Some(key) = &key {
+            if key.argc().map_or(false, |count| count != 1) {
+                bail!(key.span(), "key function must have exactly one parameter");
+            }
+        }
+        if let Some(by) = &by {
+            if by.argc().map_or(false, |count| count != 2) {
+                bail!(
+                    by.span(),
+                    "comparison function must have exactly two parameters"
+                );
+            }
+        }
+
+        let mut out = EcoVec::new();
+        let mut seen: Vec<Value> = Vec::new();
+        'items: for item in self.iter() {
+            if let Some(by) = &by {
+                for other in &seen {
+                    let args = Args::new(by.span(), [other.clone(), item.clone()]);
+                    if by.call(vm, args)?.cast::<bool>().at(by.span())? {
+                        continue 'items;
+                    }
+                }
+                seen.push(item.clone());
+            } else {
+                let key_value = match &key {
+                    Some(key) => {
+                        let args = Args::new(key.span(), [item.clone()]);
+                        key.call(vm, args)?
+                    }
+                    None => item.clone(),
+                };
+                if seen.contains(&key_value) {
+                    continue;
+                }
+                seen.push(key_value);
+            }
+            out.push(item.clone());
+        }
+
+        Ok(Self::from_vec(out))
+    }
+
     /// Whether any element matches.
     pub fn any(&self, vm: &mut Vm, func: Func) -> SourceResult<bool> {
         if func.argc().map_or(false, |count| count != 1) {
@@ -286,14 +409,60 @@ impl Array {
 
     /// Return a sorted version of this array.
     ///
-    /// Returns an error if two values could not be compared.
-    pub fn sorted(&self) -> StrResult<Self> {
-        let mut result = Ok(());
-        let mut vec = self.0.clone();
-        vec.make_mut().sort_by(|a, b| {
+    /// Optionally a key function can be given, in which case the array is
+    /// sorted by the keys instead of the values themselves; the key function
+    /// is only called once per element, not once per comparison. If a
+    /// custom `by` comparator is given instead, it is called with two
+    /// elements at a time and should return whether the first should be
+    /// ordered before the second.
+    ///
+    /// Returns an error if two values (or keys) could not be compared.
+    pub fn sorted(
+        &self,
+        vm: &mut Vm,
+        span: Span,
+        key: Option<Func>,
+        by: Option<Func>,
+    ) -> SourceResult<Self> {
+        if let Some(by) = &by {
+            if by.argc().map_or(false, |count| count != 2) {
+                bail!(
+                    by.span(),
+                    "comparison function must have exactly two parameters"
+                );
+            }
+            if key.is_some() {
+                bail!(by.span(), "cannot use both `key` and `by`");
+            }
+            return self.sorted_by(vm, by.clone());
+        }
+
+        if let Some(key) = &key {
+            if key.argc().map_or(false, |count| count != 1) {
+                bail!(key.span(), "key function must have exactly one parameter");
+            }
+        }
+
+        // Decorate-sort-undecorate: compute each element's key exactly once
+        // up front instead of re-invoking a user `key` function on every
+        // comparison the sort performs.
+        let mut decorated = Vec::with_capacity(self.0.len());
+        for item in self.iter() {
+            let key_value = match &key {
+                Some(key) => {
+                    let args = Args::new(key.span(), [item.clone()]);
+                    key.call(vm, args)?
+                }
+                None => item.clone(),
+            };
+            decorated.push((key_value, item.clone()));
+        }
+
+        let mut cmp_error = Ok(());
+        decorated.sort_by(|(a, _), (b, _)| {
             a.partial_cmp(b).unwrap_or_else(|| {
-                if result.is_ok() {
-                    result = Err(eco_format!(
+                if cmp_error.is_ok() {
+                    cmp_error = Err(eco_format!(
                         "cannot order {} and {}",
                         a.type_name(),
                         b.type_name(),
@@ -302,7 +471,47 @@ impl Array {
                 Ordering::Equal
             })
         });
-        result.map(|_| Self::from_vec(vec))
+        cmp_error.at(span)?;
+
+        Ok(decorated.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Sort this array with a custom two-argument comparison function.
+    ///
+    /// `by` is called with `(a, b)` and should return whether `a` should be
+    /// ordered before `b`. When `by(a, b)` is false, `by` is also called
+    /// with the arguments swapped to check the reverse direction, so
+    /// elements are only considered equal (and keep their input order)
+    /// when `by` says neither should precede the other.
+    fn sorted_by(&self, vm: &mut Vm, by: Func) -> SourceResult<Self> {
+        let mut vec = self.0.clone();
+        let mut error: SourceResult<()> = Ok(());
+        vec.make_mut().sort_by(|a, b| {
+            if error.is_err() {
+                return Ordering::Equal;
+            }
+            let mut call = |a: &Value, b: &Value| {
+                let args = Args::new(by.span(), [a.clone(), b.clone()]);
+                by.call(vm, args)?.cast::<bool>().at(by.span())
+            };
+            match call(a, b) {
+                Ok(true) => Ordering::Less,
+                Ok(false) => match call(b, a) {
+                    Ok(true) => Ordering::Greater,
+                    Ok(false) => Ordering::Equal,
+                    Err(err) => {
+                        error = Err(err);
+                        Ordering::Equal
+                    }
+                },
+                Err(err) => {
+                    error = Err(err);
+                    Ordering::Equal
+                }
+            }
+        });
+        error?;
+        Ok(Self::from_vec(vec))
     }
 
     /// Repeat this array `n` times.
@@ -315,6 +524,52 @@ impl Array {
         Ok(self.iter().cloned().cycle().take(count).collect())
     }
 
+    /// Start a lazy chain of `map`/`filter`/... adaptors over this array
+    /// that only materializes when collected or consumed by a terminal.
+    pub fn lazy(&self) -> Iter {
+        Iter::new(self.clone())
+    }
+
+    /// Split this array into chunks of the given size. The last chunk may
+    /// be shorter if the length does not divide evenly.
+    pub fn chunks(&self, size: i64) -> StrResult<Self> {
+        let size = Self::validate_chunk_size(size)?;
+        Ok(self
+            .0
+            .chunks(size)
+            .map(|chunk| Value::Array(Self::from_vec(chunk.into())))
+            .collect())
+    }
+
+    /// Return all overlapping contiguous sub-slices of the given length.
+    /// Empty if the array is shorter than `size`.
+    pub fn windows(&self, size: i64) -> StrResult<Self> {
+        let size = Self::validate_chunk_size(size)?;
+        Ok(self
+            .0
+            .windows(size)
+            .map(|window| Value::Array(Self::from_vec(window.into())))
+            .collect())
+    }
+
+    /// Pair up the elements of this array with those of another, truncating
+    /// to the length of the shorter one.
+    pub fn zip(&self, other: Self) -> Self {
+        self.iter()
+            .cloned()
+            .zip(other)
+            .map(|(a, b)| Value::Array(Self::from_vec(eco_vec![a, b])))
+            .collect()
+    }
+
+    /// Validate and convert a user-provided chunk/window size.
+    fn validate_chunk_size(size: i64) -> StrResult<usize> {
+        usize::try_from(size)
+            .ok()
+            .filter(|&size| size >= 1)
+            .ok_or_else(|| eco_format!("size must be at least one, found {}", size))
+    }
+
     /// Extract a slice of the whole array.
     pub fn as_slice(&self) -> &[Value] {
         self.0.as_slice()
@@ -327,8 +582,12 @@ impl Array {
 
     /// Resolve an index.
     fn locate(&self, index: i64) -> Option<usize> {
-        usize::try_from(if index >= 0 { index } else { self.len().checked_add(index)? })
-            .ok()
+        usize::try_from(if index >= 0 {
+            index
+        } else {
+            self.len().checked_add(index)?
+        })
+        .ok()
     }
 }
 
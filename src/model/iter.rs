@@ -0,0 +1,300 @@
+use std::fmt::{self, Debug, Formatter};
+
+use ecow::EcoVec;
+
+use super::{Args, Array, Func, Value, Vm};
+use crate::diag::{bail, At, SourceResult};
+
+/// A lazy, chainable view over an array.
+///
+/// Unlike the eager transformers on [`Array`] (`map`, `filter`, ...), an
+/// `Iter` only records the chain of adaptors that should be applied and
+/// defers any work until it is materialized with [`Iter::to_array`] or
+/// consumed by a terminal like [`Iter::first`], [`Iter::find`],
+/// [`Iter::fold`], [`Iter::any`], or [`Iter::all`]. This means a pipeline
+/// such as `arr.lazy().filter(f).map(g).first(vm)` never builds the
+/// intermediate filtered array and stops calling `g` as soon as the first
+/// result is found.
+#[derive(Clone, PartialEq, Hash)]
+pub struct Iter {
+    base: Array,
+    ops: EcoVec<Adaptor>,
+}
+
+/// A single deferred operation in an [`Iter`] chain.
+#[derive(Clone, PartialEq, Hash)]
+enum Adaptor {
+    Map(Func),
+    Filter(Func),
+    Enumerate,
+    Take(i64),
+    Skip(i64),
+    Flatten,
+}
+
+impl Debug for Iter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Iter")
+            .field("base", &self.base)
+            .field("ops", &self.ops.len())
+            .finish()
+    }
+}
+
+/// Whether a terminal consumer wants to see more elements.
+enum Flow {
+    Continue,
+    Break,
+}
+
+impl Iter {
+    /// Start a lazy chain over an array.
+    pub fn new(base: Array) -> Self {
+        Self {
+            base,
+            ops: EcoVec::new(),
+        }
+    }
+
+    /// Append an adaptor, returning a new chain (the original is untouched).
+    fn with(&self, op: Adaptor) -> Self {
+        let mut ops = self.ops.clone();
+        ops.push(op);
+        Self {
+            base: self.base.clone(),
+            ops,
+        }
+    }
+
+    /// Defer a transformation of each item with a function.
+    pub fn map(&self, func: Func) -> SourceResult<Self> {
+        if func.argc().map_or(false, |count| !(1..=2).contains(&count)) {
+            bail!(func.span(), "function must have one or two parameters");
+        }
+        Ok(self.with(Adaptor::Map(func)))
+    }
+
+    /// Defer keeping only the items for which the function returns true.
+    pub fn filter(&self, func: Func) -> SourceResult<Self> {
+        if func.argc().map_or(false, |count| count != 1) {
+            bail!(func.span(), "function must have exactly one parameter");
+        }
+        Ok(self.with(Adaptor::Filter(func)))
+    }
+
+    /// Defer pairing each item with its index.
+    pub fn enumerate(&self) -> Self {
+        self.with(Adaptor::Enumerate)
+    }
+
+    /// Defer stopping the chain after the first `n` items.
+    pub fn take(&self, n: i64) -> Self {
+        self.with(Adaptor::Take(n))
+    }
+
+    /// Defer skipping the first `n` items.
+    pub fn skip(&self, n: i64) -> Self {
+        self.with(Adaptor::Skip(n))
+    }
+
+    /// Defer flattening one level of nested arrays.
+    ///
+    /// Unlike the eager [`Array::flatten`], which recurses into arbitrarily
+    /// deeply nested arrays, this only unwraps a single level per call;
+    /// chain multiple `flatten`s to go deeper.
+    pub fn flatten(&self) -> Self {
+        self.with(Adaptor::Flatten)
+    }
+
+    /// Materialize the chain into a concrete array.
+    pub fn to_array(&self, vm: &mut Vm) -> SourceResult<Array> {
+        let mut out = EcoVec::new();
+        self.drive(vm, |_, value| {
+            out.push(value);
+            Ok(Flow::Continue)
+        })?;
+        Ok(Array::from_vec(out))
+    }
+
+    /// Return the first produced element, short-circuiting the chain as
+    /// soon as one is available.
+    pub fn first(&self, vm: &mut Vm) -> SourceResult<Option<Value>> {
+        let mut found = None;
+        self.drive(vm, |_, value| {
+            found = Some(value);
+            Ok(Flow::Break)
+        })?;
+        Ok(found)
+    }
+
+    /// Return the first produced element matching a predicate, stopping the
+    /// chain at the first hit.
+    pub fn find(&self, vm: &mut Vm, func: Func) -> SourceResult<Option<Value>> {
+        if func.argc().map_or(false, |count| count != 1) {
+            bail!(func.span(), "function must have exactly one parameter");
+        }
+        let mut found = None;
+        self.drive(vm, |vm, value| {
+            let args = Args::new(func.span(), [value.clone()]);
+            if func.call(vm, args)?.cast::<bool>().at(func.span())? {
+                found = Some(value);
+                Ok(Flow::Break)
+            } else {
+                Ok(Flow::Continue)
+            }
+        })?;
+        Ok(found)
+    }
+
+    /// Fold every produced element into one with a function.
+    pub fn fold(&self, vm: &mut Vm, init: Value, func: Func) -> SourceResult<Value> {
+        if func.argc().map_or(false, |count| count != 2) {
+            bail!(func.span(), "function must have exactly two parameters");
+        }
+        let mut acc = init;
+        self.drive(vm, |vm, value| {
+            let args = Args::new(func.span(), [acc.clone(), value]);
+            acc = func.call(vm, args)?;
+            Ok(Flow::Continue)
+        })?;
+        Ok(acc)
+    }
+
+    /// Whether any produced element matches, stopping the chain at the
+    /// first hit.
+    pub fn any(&self, vm: &mut Vm, func: Func) -> SourceResult<bool> {
+        if func.argc().map_or(false, |count| count != 1) {
+            bail!(func.span(), "function must have exactly one parameter");
+        }
+        let mut hit = false;
+        self.drive(vm, |vm, value| {
+            let args = Args::new(func.span(), [value]);
+            if func.call(vm, args)?.cast::<bool>().at(func.span())? {
+                hit = true;
+                Ok(Flow::Break)
+            } else {
+                Ok(Flow::Continue)
+            }
+        })?;
+        Ok(hit)
+    }
+
+    /// Whether all produced elements match, stopping the chain at the
+    /// first miss.
+    pub fn all(&self, vm: &mut Vm, func: Func) -> SourceResult<bool> {
+        if func.argc().map_or(false, |count| count != 1) {
+            bail!(func.span(), "function must have exactly one parameter");
+        }
+        let mut ok = true;
+        self.drive(vm, |vm, value| {
+            let args = Args::new(func.span(), [value]);
+            if func.call(vm, args)?.cast::<bool>().at(func.span())? {
+                Ok(Flow::Continue)
+            } else {
+                ok = false;
+                Ok(Flow::Break)
+            }
+        })?;
+        Ok(ok)
+    }
+
+    /// Drive every element of the base array through the adaptor chain,
+    /// calling `visit` for each value that survives it, until `visit` asks
+    /// to stop or the base array is exhausted.
+    fn drive(
+        &self,
+        vm: &mut Vm,
+        mut visit: impl FnMut(&mut Vm, Value) -> SourceResult<Flow>,
+    ) -> SourceResult<()> {
+        let mut counters = vec![0i64; self.ops.len()];
+        for item in self.base.iter().cloned() {
+            match self.push(vm, 0, &mut counters, item, &mut visit)? {
+                Flow::Continue => {}
+                Flow::Break => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a single value through the adaptor chain starting at `stage`.
+    fn push(
+        &self,
+        vm: &mut Vm,
+        stage: usize,
+        counters: &mut [i64],
+        value: Value,
+        visit: &mut impl FnMut(&mut Vm, Value) -> SourceResult<Flow>,
+    ) -> SourceResult<Flow> {
+        let Some(op) = self.ops.get(stage) else {
+            return visit(vm, value);
+        };
+
+        match op {
+            Adaptor::Map(func) => {
+                let enumerate = func.argc() == Some(2);
+                let mut args = Args::new(func.span(), []);
+                if enumerate {
+                    args.push(func.span(), Value::Int(counters[stage]));
+                }
+                args.push(func.span(), value);
+                counters[stage] += 1;
+                let mapped = func.call(vm, args)?;
+                self.push(vm, stage + 1, counters, mapped, visit)
+            }
+            Adaptor::Filter(func) => {
+                let args = Args::new(func.span(), [value.clone()]);
+                if func.call(vm, args)?.cast::<bool>().at(func.span())? {
+                    self.push(vm, stage + 1, counters, value, visit)
+                } else {
+                    Ok(Flow::Continue)
+                }
+            }
+            Adaptor::Enumerate => {
+                let i = counters[stage];
+                counters[stage] += 1;
+                let mut pair = EcoVec::with_capacity(2);
+                pair.push(Value::Int(i));
+                pair.push(value);
+                self.push(
+                    vm,
+                    stage + 1,
+                    counters,
+                    Value::Array(Array::from_vec(pair)),
+                    visit,
+                )
+            }
+            Adaptor::Take(n) => {
+                if counters[stage] >= *n {
+                    return Ok(Flow::Break);
+                }
+                counters[stage] += 1;
+                let flow = self.push(vm, stage + 1, counters, value, visit)?;
+                if counters[stage] >= *n {
+                    return Ok(Flow::Break);
+                }
+                Ok(flow)
+            }
+            Adaptor::Skip(n) => {
+                if counters[stage] < *n {
+                    counters[stage] += 1;
+                    Ok(Flow::Continue)
+                } else {
+                    self.push(vm, stage + 1, counters, value, visit)
+                }
+            }
+            Adaptor::Flatten => {
+                if let Value::Array(nested) = &value {
+                    for inner in nested.iter().cloned() {
+                        match self.push(vm, stage + 1, counters, inner, visit)? {
+                            Flow::Continue => {}
+                            Flow::Break => return Ok(Flow::Break),
+                        }
+                    }
+                    Ok(Flow::Continue)
+                } else {
+                    self.push(vm, stage + 1, counters, value, visit)
+                }
+            }
+        }
+    }
+}